@@ -0,0 +1,186 @@
+use rspack::builder::{Builder, Devtool};
+use rspack_core::{
+    Compiler, ModuleOptions, ModuleRule, ModuleRuleEffect, ModuleRuleUse, ModuleRuleUseLoader,
+    OutputOptions, Resolve, RuleSetCondition, TsconfigOptions, TsconfigReferences,
+};
+use rspack_fs::{FsWatcherIgnored, FsWatcherOptions, MemoryFileSystem, NativeFileSystem};
+use rspack_plugin_html::{HtmlRspackPlugin, config::HtmlRspackPluginOptions};
+use rspack_regex::RspackRegex;
+use std::{fs, sync::Arc, time::Duration};
+
+use crate::watcher::Watching;
+
+/// Configuration for the compiler/server pipeline, covering the pieces that
+/// used to be hardwired into `dev_routes::routes`. Defaults reproduce the
+/// crate's original setup (a single TypeScript entry compiled with the swc
+/// loader, serving `./frontend` in development mode) but every piece can be
+/// overridden, so the crate works as a library for other projects and not
+/// just its own frontend.
+pub struct DevServerConfig {
+    mode: String,
+    devtool: Devtool,
+    context: String,
+    entries: Vec<(String, String)>,
+    module_rules: Vec<ModuleRule>,
+    resolve: Resolve,
+    ignored: FsWatcherIgnored,
+    watcher_options: FsWatcherOptions,
+    ready_timeout: Duration,
+}
+
+impl Default for DevServerConfig {
+    fn default() -> Self {
+        Self {
+            mode: "development".to_string(),
+            devtool: Devtool::InlineSourceMap,
+            context: env!("CARGO_MANIFEST_DIR").to_string(),
+            entries: vec![("main".to_string(), "./frontend/index.ts".to_string())],
+            module_rules: vec![ModuleRule {
+                test: Some(RuleSetCondition::Regexp(
+                    RspackRegex::new("\\.ts$").unwrap(),
+                )),
+                effect: ModuleRuleEffect {
+                    r#use: ModuleRuleUse::Array(vec![ModuleRuleUseLoader {
+                        loader: "builtin:swc-loader".to_string(),
+                        options: Some(fs::read_to_string(".swcrc").unwrap()),
+                    }]),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            resolve: Resolve {
+                tsconfig: Some(TsconfigOptions {
+                    config_file: "./tsconfig.json".into(),
+                    references: TsconfigReferences::Auto,
+                }),
+                ..Default::default()
+            },
+            ignored: FsWatcherIgnored::Regex(
+                RspackRegex::new(r#"[\/](?:\.git|node_modules)[\/]"#).unwrap(),
+            ),
+            watcher_options: FsWatcherOptions {
+                follow_symlinks: false,
+                poll_interval: None,
+                aggregate_timeout: None,
+            },
+            ready_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl DevServerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mode(mut self, mode: impl Into<String>) -> Self {
+        self.mode = mode.into();
+        self
+    }
+
+    pub fn devtool(mut self, devtool: Devtool) -> Self {
+        self.devtool = devtool;
+        self
+    }
+
+    pub fn context(mut self, context: impl Into<String>) -> Self {
+        self.context = context.into();
+        self
+    }
+
+    /// Add an entry, keeping whatever entries were configured before it.
+    /// The default entry is replaced by calling [`Self::entries`] instead.
+    pub fn entry(mut self, name: impl Into<String>, request: impl Into<String>) -> Self {
+        self.entries.push((name.into(), request.into()));
+        self
+    }
+
+    pub fn entries(mut self, entries: Vec<(String, String)>) -> Self {
+        self.entries = entries;
+        self
+    }
+
+    /// Add a module rule, keeping whatever rules were configured before it.
+    /// The default `.ts` swc rule is replaced by calling [`Self::module_rules`].
+    pub fn module_rule(mut self, rule: ModuleRule) -> Self {
+        self.module_rules.push(rule);
+        self
+    }
+
+    pub fn module_rules(mut self, rules: Vec<ModuleRule>) -> Self {
+        self.module_rules = rules;
+        self
+    }
+
+    pub fn resolve(mut self, resolve: Resolve) -> Self {
+        self.resolve = resolve;
+        self
+    }
+
+    pub fn ignored(mut self, ignored: FsWatcherIgnored) -> Self {
+        self.ignored = ignored;
+        self
+    }
+
+    pub fn watcher_options(mut self, options: FsWatcherOptions) -> Self {
+        self.watcher_options = options;
+        self
+    }
+
+    pub fn ready_timeout(mut self, timeout: Duration) -> Self {
+        self.ready_timeout = timeout;
+        self
+    }
+
+    fn build_compiler<FS>(&self, output: OutputOptions, output_filesystem: Arc<FS>) -> Compiler {
+        let mut builder = Compiler::builder()
+            .mode(self.mode.clone().into())
+            .devtool(self.devtool)
+            .context(self.context.clone())
+            .output(output)
+            .resolve(self.resolve.clone())
+            .module(ModuleOptions {
+                rules: self.module_rules.clone(),
+                ..Default::default()
+            })
+            .plugin(Box::new(HtmlRspackPlugin::new(
+                HtmlRspackPluginOptions::default(),
+            )))
+            .output_filesystem(output_filesystem)
+            .enable_loader_swc();
+        for (name, request) in &self.entries {
+            builder = builder.entry(name.clone(), request.clone());
+        }
+        builder.build().unwrap()
+    }
+
+    /// Build a [`Watching`] backed by an in-memory filesystem, watching the
+    /// context directory and rebuilding on every source change. Drives the
+    /// dev server (see [`crate::dev_routes::routes`]).
+    pub fn into_watching(self) -> Watching {
+        let compiler = self.build_compiler(
+            OutputOptions::builder().path("/"),
+            Arc::new(MemoryFileSystem::default()),
+        );
+        Watching::with_ready_timeout(
+            compiler,
+            Some(self.watcher_options),
+            Some(self.ignored),
+            self.ready_timeout,
+        )
+    }
+
+    /// Compile once to a real on-disk output filesystem with no watcher
+    /// attached, for production builds (see [`crate::prod_routes::routes`]).
+    pub async fn build_once(self, output_path: impl Into<String>) {
+        let output_path = output_path.into();
+        let mut compiler = self.build_compiler(
+            OutputOptions::builder().path(output_path),
+            Arc::new(NativeFileSystem::new(false)),
+        );
+        compiler.build().await.ok();
+        for diagnostic in compiler.compilation.get_errors() {
+            tracing::error!("{:?}", diagnostic);
+        }
+    }
+}
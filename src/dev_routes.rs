@@ -1,20 +1,76 @@
 use axum::{
     Router,
-    extract::{Path, State},
+    body::Body,
+    extract::{
+        Path, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
     http::{HeaderMap, HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
-use rspack::builder::{Builder, Devtool};
-use rspack_core::{
-    Compiler, ModuleOptions, ModuleRule, ModuleRuleEffect, ModuleRuleUse, ModuleRuleUseLoader,
-    OutputOptions, Resolve, RuleSetCondition, TsconfigOptions, TsconfigReferences,
-};
-use rspack_fs::MemoryFileSystem;
-use rspack_plugin_html::{HtmlRspackPlugin, config::HtmlRspackPluginOptions};
-use rspack_regex::RspackRegex;
-use std::{env, fs, sync::Arc};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::broadcast;
+use tokio_util::io::ReaderStream;
+
+use crate::optional_watch::OptionalWatchError;
+use crate::watcher::{Asset, AssetBody, Diagnostic, ReloadEvent, SyncTimeoutError, Watching};
+
+/// Path the injected client script connects to for live-reload notifications.
+const DEV_WS_PATH: &str = "/__dev_ws";
 
-use crate::watcher::Watching;
+/// Path serving the latest build's diagnostics as JSON.
+const DEV_ERRORS_PATH: &str = "/__dev_errors";
+
+/// Inlined into the served index page and the error overlay page (other
+/// HTML assets are served unchanged); opens the dev-server WebSocket,
+/// reloads the page once a build succeeds, and renders an error overlay
+/// in place when a build fails, reconnecting after the server restarts.
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>(function(){
+var url=(location.protocol==="https:"?"wss://":"ws://")+location.host+"/__dev_ws";
+var overlayId="__dev_error_overlay";
+function hideOverlay(){
+  var existing=document.getElementById(overlayId);
+  if (existing) existing.remove();
+}
+function showOverlay(diagnostics){
+  hideOverlay();
+  var overlay=document.createElement("pre");
+  overlay.id=overlayId;
+  overlay.style.cssText="position:fixed;inset:0;margin:0;background:#1e1e1eee;color:#ff6b6b;"
+    + "font-family:monospace;font-size:13px;padding:2rem;overflow:auto;z-index:2147483647;white-space:pre-wrap;";
+  overlay.textContent=diagnostics.map(function(d){return "["+d.severity+"] "+d.message;}).join("\n\n");
+  document.body.appendChild(overlay);
+}
+function showErrors(){
+  fetch("/__dev_errors").then(function(res){return res.json();}).then(function(body){showOverlay(body.diagnostics);});
+}
+function connect(){
+  var ws=new WebSocket(url);
+  ws.onmessage=function(event){
+    if (event.data==="errors") {
+      showErrors();
+    } else {
+      location.reload();
+    }
+  };
+  ws.onclose=function(){setTimeout(connect,1000);};
+}
+connect();
+})();</script>"#;
+
+fn inject_live_reload_script(mut content: Vec<u8>) -> Vec<u8> {
+    let needle = b"</body>";
+    match content
+        .windows(needle.len())
+        .position(|window| window == needle)
+    {
+        Some(position) => {
+            content.splice(position..position, LIVE_RELOAD_SCRIPT.bytes());
+        }
+        None => content.extend_from_slice(LIVE_RELOAD_SCRIPT.as_bytes()),
+    }
+    content
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -26,6 +82,9 @@ pub enum Error {
 
     #[error(transparent)]
     InvalidHeaderValue(#[from] axum::http::header::InvalidHeaderValue),
+
+    #[error("range not satisfiable")]
+    RangeNotSatisfiable,
 }
 
 impl From<StatusCode> for Error {
@@ -35,6 +94,20 @@ impl From<StatusCode> for Error {
     }
 }
 
+impl From<OptionalWatchError> for Error {
+    #[inline]
+    fn from(_: OptionalWatchError) -> Self {
+        Error::StatusCode(StatusCode::SERVICE_UNAVAILABLE)
+    }
+}
+
+impl From<SyncTimeoutError> for Error {
+    #[inline]
+    fn from(_: SyncTimeoutError) -> Self {
+        Error::StatusCode(StatusCode::GATEWAY_TIMEOUT)
+    }
+}
+
 impl IntoResponse for Error {
     #[inline]
     fn into_response(self) -> Response {
@@ -43,6 +116,7 @@ impl IntoResponse for Error {
                 Some(reason) => (code, reason).into_response(),
                 None => code.into_response(),
             },
+            Error::RangeNotSatisfiable => StatusCode::RANGE_NOT_SATISFIABLE.into_response(),
             #[cfg(debug_assertions)]
             error => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#?}", error)).into_response(),
             #[cfg(not(debug_assertions))]
@@ -51,78 +125,253 @@ impl IntoResponse for Error {
     }
 }
 
-async fn get_index(State(watching): State<Watching>) -> Result<(HeaderMap, Vec<u8>), Error> {
-    if let Some((mime_type, content)) = watching.get_asset("index.html").await {
-        Ok((
-            HeaderMap::from_iter([(
-                header::CONTENT_TYPE,
-                HeaderValue::from_str(mime_type.as_ref())?,
-            )]),
-            content,
-        ))
+async fn get_index(
+    State(watching): State<Watching>,
+    request_headers: HeaderMap,
+) -> Result<Response, Error> {
+    watching.sync().await?;
+
+    let diagnostics = watching.diagnostics().await;
+    if diagnostics.iter().any(Diagnostic::is_error) {
+        return Ok(error_overlay_page(&diagnostics));
+    }
+
+    if let Some(mut asset) = watching.get_asset("index.html").await? {
+        let AssetBody::Bytes(content) = asset.body else {
+            unreachable!("the dev server's output filesystem only ever produces buffered assets")
+        };
+        let content = inject_live_reload_script(content);
+        asset.len = content.len() as u64;
+        asset.body = AssetBody::Bytes(content);
+        asset_response(&request_headers, asset).await
     } else {
         Err(StatusCode::NOT_FOUND)?
     }
 }
 
+async fn dev_errors(State(watching): State<Watching>) -> axum::Json<DevErrorsResponse> {
+    let diagnostics = watching.diagnostics().await;
+    let has_errors = diagnostics.iter().any(Diagnostic::is_error);
+    axum::Json(DevErrorsResponse {
+        has_errors,
+        diagnostics,
+    })
+}
+
+#[derive(serde::Serialize)]
+struct DevErrorsResponse {
+    has_errors: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// A standalone HTML page shown instead of the app while the latest build
+/// has errors. Carries the same live-reload client so it dismisses itself
+/// as soon as a build succeeds.
+fn error_overlay_page(diagnostics: &[Diagnostic]) -> Response {
+    let items: String = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            format!(
+                "<pre>[{severity}] {message}</pre>",
+                severity = html_escape(diagnostic.severity.as_str()),
+                message = html_escape(&diagnostic.message),
+            )
+        })
+        .collect();
+    let body = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Compilation Error</title></head>\
+         <body style=\"font-family:monospace;background:#1e1e1e;color:#ff6b6b;padding:2rem;\">\
+         <h1>Compilation failed</h1>{items}{LIVE_RELOAD_SCRIPT}</body></html>"
+    );
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/html; charset=utf-8"),
+    );
+    (StatusCode::OK, headers, body).into_response()
+}
+
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
 async fn get_asset(
     State(watching): State<Watching>,
     Path(path): Path<String>,
-) -> Result<(HeaderMap, Vec<u8>), Error> {
-    if let Some((mime_type, content)) = watching.get_asset(path).await {
-        Ok((
-            HeaderMap::from_iter([(
-                header::CONTENT_TYPE,
-                HeaderValue::from_str(mime_type.as_ref())?,
-            )]),
-            content,
-        ))
+    request_headers: HeaderMap,
+) -> Result<Response, Error> {
+    watching.sync().await?;
+    if let Some(asset) = watching.get_asset(path).await? {
+        asset_response(&request_headers, asset).await
     } else {
         Err(StatusCode::NOT_FOUND)?
     }
 }
 
-pub fn routes() -> Router {
-    let compiler = Compiler::builder()
-        .mode("development".into())
-        .devtool(Devtool::InlineSourceMap)
-        .context(env!("CARGO_MANIFEST_DIR"))
-        .entry("main", "./frontend/index.ts")
-        .output(OutputOptions::builder().path("/"))
-        .resolve(Resolve {
-            tsconfig: Some(TsconfigOptions {
-                config_file: "./tsconfig.json".into(),
-                references: TsconfigReferences::Auto,
-            }),
-            ..Default::default()
-        })
-        .module(ModuleOptions {
-            rules: vec![ModuleRule {
-                test: Some(RuleSetCondition::Regexp(
-                    RspackRegex::new("\\.ts$").unwrap(),
-                )),
-                effect: ModuleRuleEffect {
-                    r#use: ModuleRuleUse::Array(vec![ModuleRuleUseLoader {
-                        loader: "builtin:swc-loader".to_string(),
-                        options: Some(fs::read_to_string(".swcrc").unwrap()),
-                    }]),
-                    ..Default::default()
-                },
-                ..Default::default()
-            }],
-            ..Default::default()
-        })
-        .plugin(Box::new(HtmlRspackPlugin::new(
-            HtmlRspackPluginOptions::default(),
-        )))
-        .output_filesystem(Arc::new(MemoryFileSystem::default()))
-        .enable_loader_swc()
-        .build()
-        .unwrap();
-    let watching = Watching::new(compiler, None, None);
+/// Build the response for an [`Asset`], honoring conditional-GET
+/// (`If-None-Match`/`If-Modified-Since`) and `Range` requests. Shared with
+/// [`crate::prod_routes`], which reads assets straight off disk instead of
+/// through a [`Watching`]. Assets backed by a file on disk are streamed
+/// rather than buffered into memory.
+pub(crate) async fn asset_response(request_headers: &HeaderMap, asset: Asset) -> Result<Response, Error> {
+    let etag = format!("\"{:x}-{:x}\"", asset.generation, asset.len);
+    let last_modified = httpdate::fmt_http_date(asset.modified);
+
+    if is_not_modified(request_headers, &etag, asset.modified) {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, HeaderValue::from_str(&etag)?);
+        headers.insert(header::LAST_MODIFIED, HeaderValue::from_str(&last_modified)?);
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(asset.mime.as_ref())?,
+    );
+    headers.insert(header::ETAG, HeaderValue::from_str(&etag)?);
+    headers.insert(header::LAST_MODIFIED, HeaderValue::from_str(&last_modified)?);
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    if let Some(range) = request_headers.get(header::RANGE) {
+        return partial_content(range, asset.body, asset.len, headers).await;
+    }
+
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(asset.len));
+    Ok((StatusCode::OK, headers, asset_body(asset.body).await?).into_response())
+}
+
+/// Turn an [`AssetBody`] into a response body: an owned buffer is wrapped
+/// directly, a file on disk is streamed instead of being read into memory.
+async fn asset_body(body: AssetBody) -> Result<Body, Error> {
+    Ok(match body {
+        AssetBody::Bytes(content) => Body::from(content),
+        AssetBody::File(path) => {
+            let file = tokio::fs::File::open(&path).await?;
+            Body::from_stream(ReaderStream::new(file))
+        }
+    })
+}
+
+fn is_not_modified(request_headers: &HeaderMap, etag: &str, modified: std::time::SystemTime) -> bool {
+    if let Some(if_none_match) = request_headers.get(header::IF_NONE_MATCH) {
+        return if_none_match.to_str().map(|value| value == etag).unwrap_or(false);
+    }
+    if let Some(if_modified_since) = request_headers.get(header::IF_MODIFIED_SINCE)
+        && let Ok(if_modified_since) = if_modified_since.to_str()
+        && let Ok(since) = httpdate::parse_http_date(if_modified_since)
+    {
+        return modified <= since;
+    }
+    false
+}
+
+/// Serve a single-range `206 Partial Content` response, or `416` if the
+/// `Range` header is not a valid `bytes=` range or is unsatisfiable.
+/// Multi-range requests aren't supported and are rejected with `416` as
+/// well. A `Range` header that isn't visible ASCII is ignored and the whole
+/// asset is served with `200`.
+async fn partial_content(
+    range: &HeaderValue,
+    body: AssetBody,
+    total: u64,
+    mut headers: HeaderMap,
+) -> Result<Response, Error> {
+    let Ok(range) = range.to_str() else {
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(total));
+        return Ok((StatusCode::OK, headers, asset_body(body).await?).into_response());
+    };
+    let Some((start, end)) = parse_byte_range(range, total) else {
+        return Err(Error::RangeNotSatisfiable);
+    };
+
+    let len = end - start + 1;
+    headers.insert(
+        header::CONTENT_RANGE,
+        HeaderValue::from_str(&format!("bytes {start}-{end}/{total}"))?,
+    );
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+
+    let body = match body {
+        AssetBody::Bytes(content) => Body::from(content[start as usize..=end as usize].to_vec()),
+        AssetBody::File(path) => {
+            let mut file = tokio::fs::File::open(&path).await?;
+            file.seek(std::io::SeekFrom::Start(start)).await?;
+            Body::from_stream(ReaderStream::new(file.take(len)))
+        }
+    };
+    Ok((StatusCode::PARTIAL_CONTENT, headers, body).into_response())
+}
+
+/// Parse a single `bytes=start-end` range against a known total length.
+/// Returns `None` for anything unsatisfiable or for multi-range requests,
+/// which this server doesn't support.
+fn parse_byte_range(value: &str, total: u64) -> Option<(u64, u64)> {
+    let spec = value.strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    if start.is_empty() {
+        let suffix_len: u64 = end.parse().ok()?;
+        if suffix_len == 0 || total == 0 {
+            return None;
+        }
+        let suffix_len = suffix_len.min(total);
+        Some((total - suffix_len, total - 1))
+    } else {
+        let start: u64 = start.parse().ok()?;
+        if start >= total {
+            return None;
+        }
+        let end = if end.is_empty() {
+            total - 1
+        } else {
+            end.parse::<u64>().ok()?.min(total - 1)
+        };
+        (start <= end).then_some((start, end))
+    }
+}
+
+async fn dev_ws(ws: WebSocketUpgrade, State(watching): State<Watching>) -> Response {
+    ws.on_upgrade(move |socket| handle_dev_ws(socket, watching))
+}
+
+async fn handle_dev_ws(mut socket: WebSocket, watching: Watching) {
+    let mut reload_rx = watching.subscribe_reload();
+    loop {
+        tokio::select! {
+            event = reload_rx.recv() => match event {
+                Ok(event) => {
+                    if socket.send(reload_event_to_message(&event)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(_)) => continue,
+                _ => break,
+            },
+        }
+    }
+}
+
+fn reload_event_to_message(event: &ReloadEvent) -> Message {
+    match event {
+        ReloadEvent::Reload => Message::text("reload"),
+        ReloadEvent::Errors => Message::text("errors"),
+    }
+}
 
+/// Build the dev-server `Router` around an already-running `Watching`.
+/// Use [`crate::config::DevServerConfig`] to construct one.
+pub fn routes(watching: Watching) -> Router {
     Router::new()
         .route("/", axum::routing::get(get_index))
+        .route(DEV_WS_PATH, axum::routing::get(dev_ws))
+        .route(DEV_ERRORS_PATH, axum::routing::get(dev_errors))
         .route("/{*path}", axum::routing::get(get_asset))
         .with_state(watching)
 }
@@ -4,9 +4,14 @@ use tokio::{net::TcpListener, signal};
 use tracing::{error, info};
 use tracing_subscriber::{filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
+mod config;
 mod dev_routes;
+mod optional_watch;
+mod prod_routes;
 mod watcher;
 
+use config::DevServerConfig;
+
 pub fn env(key: impl AsRef<OsStr>, default: &str) -> Result<String, env::VarError> {
     match env::var(key) {
         Ok(value) => Ok(value),
@@ -26,9 +31,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let address: SocketAddr = env("SOCKET", "127.0.0.1:3000")?.parse()?;
 
-    let app = Router::new();
+    let fallback = match env("MODE", "dev")?.as_str() {
+        "build" => {
+            let output_path = env("OUTPUT_PATH", "./dist")?;
+            DevServerConfig::new().build_once(output_path.clone()).await;
+            prod_routes::routes(output_path)
+        }
+        _ => dev_routes::routes(DevServerConfig::new().into_watching()),
+    };
 
-    let app = app.fallback_service(dev_routes::routes());
+    let app = Router::new().fallback_service(fallback);
 
     let listener: TcpListener = TcpListener::bind(&address).await.unwrap();
     info!(target: "app.server", address = %listener.local_addr()?, "listening");
@@ -0,0 +1,66 @@
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Errors produced while waiting on an [`OptionalWatch`].
+#[derive(Debug, thiserror::Error)]
+pub enum OptionalWatchError {
+    #[error("timed out waiting for value to become available")]
+    Timeout,
+
+    #[error("value will never become available: sender was dropped")]
+    Closed,
+}
+
+/// The writer half of an [`OptionalWatch`] channel.
+///
+/// Created alongside its [`OptionalWatch`] receiver via [`OptionalWatch::channel`].
+#[derive(Debug)]
+pub struct OptionalWatchSender<T>(watch::Sender<Option<T>>);
+
+impl<T> OptionalWatchSender<T> {
+    /// Publish a new value, waking any receiver suspended in [`OptionalWatch::get`].
+    pub fn set(&self, value: T) {
+        self.0.send_replace(Some(value));
+    }
+}
+
+/// A `tokio::sync::watch::Receiver<Option<T>>` whose [`get`](Self::get) suspends
+/// until the value transitions from `None` to `Some`, instead of observing `None`.
+///
+/// Used to gate requests on a resource (e.g. the first compiler build) that
+/// starts out unavailable and becomes available exactly once, after which
+/// readers always see the latest value immediately.
+#[derive(Debug, Clone)]
+pub struct OptionalWatch<T>(watch::Receiver<Option<T>>);
+
+impl<T: Clone> OptionalWatch<T> {
+    /// Create a linked sender/receiver pair, initially `None`.
+    pub fn channel() -> (OptionalWatchSender<T>, Self) {
+        let (tx, rx) = watch::channel(None);
+        (OptionalWatchSender(tx), Self(rx))
+    }
+
+    /// Suspend until the value is `Some`, then return a clone of it.
+    pub async fn get(&mut self) -> Result<T, OptionalWatchError> {
+        loop {
+            if let Some(value) = self.0.borrow().clone() {
+                return Ok(value);
+            }
+            self.0.changed().await.map_err(|_| OptionalWatchError::Closed)?;
+        }
+    }
+
+    /// Like [`Self::get`], but fails with [`OptionalWatchError::Timeout`]
+    /// instead of suspending forever.
+    pub async fn get_timeout(&mut self, timeout: Duration) -> Result<T, OptionalWatchError> {
+        tokio::time::timeout(timeout, self.get())
+            .await
+            .map_err(|_| OptionalWatchError::Timeout)?
+    }
+
+    /// Return the current value without waiting, or `None` if it hasn't
+    /// been set yet.
+    pub fn peek(&self) -> Option<T> {
+        self.0.borrow().clone()
+    }
+}
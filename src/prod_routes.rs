@@ -0,0 +1,94 @@
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+};
+use std::{
+    path::{Component, Path as StdPath, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::dev_routes::{self, Error};
+use crate::watcher::{Asset, AssetBody};
+
+/// State for the production router: just the directory a [`crate::config::DevServerConfig`]
+/// was built into once, ahead of time, with no watcher attached.
+#[derive(Clone)]
+struct ProdState {
+    root: Arc<PathBuf>,
+}
+
+/// Reject any `rel_path` that isn't a plain, relative, traversal-free path,
+/// so a request can never escape `root` onto the rest of the filesystem.
+fn sanitize_rel_path(rel_path: &str) -> Option<&StdPath> {
+    let path = StdPath::new(rel_path);
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then_some(path)
+}
+
+async fn read_asset(root: &PathBuf, rel_path: &str) -> Result<Option<Asset>, Error> {
+    let Some(rel_path) = sanitize_rel_path(rel_path) else {
+        return Ok(None);
+    };
+    let path = root.join(rel_path);
+    let metadata = match tokio::fs::metadata(&path).await {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return Ok(None),
+    };
+    let modified = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+    let mime = mime_guess::from_path(&path).first_or_octet_stream();
+    Ok(Some(Asset {
+        mime,
+        // Serve straight off disk instead of buffering the whole file into
+        // memory; dev_routes streams this with tokio::fs + ReaderStream.
+        body: AssetBody::File(path),
+        len: metadata.len(),
+        // There's no build-generation counter outside a watcher; derive one
+        // from the mtime so it actually changes when the content does,
+        // instead of repeating `len`, which the ETag already includes.
+        generation: modified
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default(),
+        modified,
+    }))
+}
+
+async fn get_index(
+    State(state): State<ProdState>,
+    request_headers: HeaderMap,
+) -> Result<Response, Error> {
+    if let Some(asset) = read_asset(&state.root, "index.html").await? {
+        dev_routes::asset_response(&request_headers, asset).await
+    } else {
+        Err(StatusCode::NOT_FOUND)?
+    }
+}
+
+async fn get_asset(
+    State(state): State<ProdState>,
+    Path(path): Path<String>,
+    request_headers: HeaderMap,
+) -> Result<Response, Error> {
+    if let Some(asset) = read_asset(&state.root, &path).await? {
+        dev_routes::asset_response(&request_headers, asset).await
+    } else {
+        Err(StatusCode::NOT_FOUND)?
+    }
+}
+
+/// Build the production `Router`, serving the output of a prior
+/// [`crate::config::DevServerConfig::build_once`] call straight off disk.
+pub fn routes(output_path: impl Into<PathBuf>) -> Router {
+    let state = ProdState {
+        root: Arc::new(output_path.into()),
+    };
+
+    Router::new()
+        .route("/", axum::routing::get(get_index))
+        .route("/{*path}", axum::routing::get(get_asset))
+        .with_state(state)
+}
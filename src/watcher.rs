@@ -6,13 +6,121 @@ use rspack_fs::{
 use rspack_paths::Utf8Path;
 use rspack_regex::RspackRegex;
 use rspack_util::fx_hash::FxHashSet;
-use std::{collections::HashSet, sync::Arc, time::SystemTime};
-use tokio::sync::RwLock;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::sync::{RwLock, broadcast, oneshot};
+
+use crate::optional_watch::{OptionalWatch, OptionalWatchError, OptionalWatchSender};
+
+/// Capacity of the live-reload broadcast channel; slow/disconnected clients
+/// simply lag and miss intermediate reloads rather than blocking a rebuild.
+const RELOAD_CHANNEL_CAPACITY: usize = 16;
+
+/// How long [`Watching::get_asset`] will wait for the first build to finish
+/// before giving up, unless overridden.
+const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long [`Watching::sync`] will wait for the watcher to observe its
+/// cookie file before giving up.
+const DEFAULT_SYNC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Filename prefix for [`Watching::sync_timeout`]'s marker files. Used to
+/// filter them back out of the file sets reported to `rebuild()`, so that
+/// every request's `sync()` call doesn't itself trigger a rebuild.
+const SYNC_COOKIE_PREFIX: &str = ".rspack-dev-sync-";
+
+/// Identifies a single [`Watching::sync`] call.
+pub type CookieId = u64;
+
+/// Returned by [`Watching::sync`] when the watcher never reported seeing the
+/// cookie file within the timeout (e.g. the filesystem watcher backend is
+/// unavailable or unreasonably slow).
+#[derive(Debug, thiserror::Error)]
+#[error("timed out waiting for the filesystem watcher to observe sync cookie {0}")]
+pub struct SyncTimeoutError(pub CookieId);
+
+/// Message pushed to connected dev-server clients after a build finishes.
+#[derive(Debug, Clone)]
+pub enum ReloadEvent {
+    /// Reload the whole page.
+    Reload,
+    /// The latest build has errors; fetch `/__dev_errors` and show the overlay.
+    Errors,
+}
+
+/// An emitted asset's bytes, either already buffered (the dev server's
+/// in-memory output filesystem has no streaming read API) or backed by a
+/// file on disk, which callers can stream instead of buffering.
+pub enum AssetBody {
+    Bytes(Vec<u8>),
+    File(std::path::PathBuf),
+}
+
+/// An emitted asset read from the output filesystem, tagged with the build
+/// that produced it so callers can do conditional-GET and range handling.
+pub struct Asset {
+    pub mime: Mime,
+    pub body: AssetBody,
+    /// Byte length of the asset, known up front regardless of `body`.
+    pub len: u64,
+    /// Build generation that produced this content; changes on every
+    /// successful or failed build attempt. Assets read off disk by
+    /// [`crate::prod_routes`] carry the file's mtime in nanoseconds instead.
+    pub generation: u64,
+    /// When the build that produced this content finished, or the file's
+    /// mtime for assets read off disk by [`crate::prod_routes`].
+    pub modified: SystemTime,
+}
+
+/// A single compilation error or warning, formatted for display in the
+/// browser error overlay or the `/__dev_errors` JSON endpoint.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Watching {
     compiler: Arc<RwLock<Compiler>>,
     watcher: Arc<RwLock<FsWatcher>>,
+    reload_tx: broadcast::Sender<ReloadEvent>,
+    generation: Arc<AtomicU64>,
+    generation_tx: Arc<OptionalWatchSender<u64>>,
+    generation_rx: OptionalWatch<u64>,
+    build_time: Arc<Mutex<SystemTime>>,
+    ready_timeout: Duration,
+    cookies: Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>,
+    cookie_counter: Arc<AtomicU64>,
+    diagnostics: Arc<RwLock<Vec<Diagnostic>>>,
 }
 
 impl Watching {
@@ -20,6 +128,15 @@ impl Watching {
         compiler: Compiler,
         options: Option<FsWatcherOptions>,
         ignored: Option<FsWatcherIgnored>,
+    ) -> Self {
+        Self::with_ready_timeout(compiler, options, ignored, DEFAULT_READY_TIMEOUT)
+    }
+
+    pub fn with_ready_timeout(
+        compiler: Compiler,
+        options: Option<FsWatcherOptions>,
+        ignored: Option<FsWatcherIgnored>,
+        ready_timeout: Duration,
     ) -> Self {
         let compiler = Arc::new(RwLock::new(compiler));
 
@@ -35,7 +152,22 @@ impl Watching {
         );
         let watcher = Arc::new(RwLock::new(watcher));
 
-        let watching = Self { compiler, watcher };
+        let (reload_tx, _) = broadcast::channel(RELOAD_CHANNEL_CAPACITY);
+        let (generation_tx, generation_rx) = OptionalWatch::channel();
+
+        let watching = Self {
+            compiler,
+            watcher,
+            reload_tx,
+            generation: Arc::new(AtomicU64::new(0)),
+            generation_tx: Arc::new(generation_tx),
+            generation_rx,
+            build_time: Arc::new(Mutex::new(SystemTime::UNIX_EPOCH)),
+            ready_timeout,
+            cookies: Arc::new(Mutex::new(HashMap::new())),
+            cookie_counter: Arc::new(AtomicU64::new(0)),
+            diagnostics: Arc::new(RwLock::new(Vec::new())),
+        };
 
         tokio::spawn({
             let watching = watching.clone();
@@ -49,86 +181,223 @@ impl Watching {
         let start_time = SystemTime::now();
         self.watcher.read().await.pause().unwrap();
 
-        self.compiler.write().await.build().await.ok();
+        {
+            let mut compiler = self.compiler.write().await;
+            compiler.build().await.ok();
+            // Bump the generation in the same critical section that swaps in
+            // the new build output, so a reader can never observe content
+            // from one build paired with the generation of another.
+            self.bump_generation();
+        }
 
         let compiler = self.compiler.read().await;
         let files = compiler.compilation.file_dependencies();
         let missing = compiler.compilation.missing_dependencies();
+        let context_dir = compiler.options.context.to_string();
+
+        let has_errors = self.record_diagnostics(&compiler).await;
 
         self.watcher
             .write()
             .await
             .watch(
                 (files.0.cloned(), files.2.cloned()),
-                (std::iter::empty(), std::iter::empty()),
+                (std::iter::once(context_dir), std::iter::empty()),
                 (missing.0.cloned(), missing.2.cloned()),
                 start_time,
                 Box::new(self.clone()),
                 Box::new(self.clone()),
             )
             .await;
+
+        self.notify_build_finished(has_errors);
     }
 
     pub async fn rebuild(&self, changed_files: HashSet<String>, deleted_files: HashSet<String>) {
         let start_time = SystemTime::now();
         self.watcher.read().await.pause().unwrap();
 
-        self.compiler
-            .write()
-            .await
-            .rebuild(changed_files, deleted_files)
-            .await
-            .ok();
+        {
+            let mut compiler = self.compiler.write().await;
+            compiler.rebuild(changed_files, deleted_files).await.ok();
+            self.bump_generation();
+        }
 
         let compiler = self.compiler.read().await;
         let files = compiler.compilation.file_dependencies();
         let missing = compiler.compilation.missing_dependencies();
+        let context_dir = compiler.options.context.to_string();
 
-        for diagnostic in compiler.compilation.get_errors() {
-            tracing::warn!("{:?}", diagnostic);
-        }
+        let has_errors = self.record_diagnostics(&compiler).await;
 
         self.watcher
             .write()
             .await
             .watch(
                 (files.1.cloned(), files.2.cloned()),
-                (std::iter::empty(), std::iter::empty()),
+                (std::iter::once(context_dir), std::iter::empty()),
                 (missing.1.cloned(), missing.2.cloned()),
                 start_time,
                 Box::new(self.clone()),
                 Box::new(self.clone()),
             )
             .await;
+
+        self.notify_build_finished(has_errors);
+    }
+
+    /// Subscribe to live-reload notifications pushed after every finished
+    /// build: [`ReloadEvent::Reload`] on success, [`ReloadEvent::Errors`] on failure.
+    pub fn subscribe_reload(&self) -> broadcast::Receiver<ReloadEvent> {
+        self.reload_tx.subscribe()
+    }
+
+    /// Snapshot of the diagnostics from the most recently finished build.
+    pub async fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.read().await.clone()
+    }
+
+    /// Log the current compilation's errors and stash its errors/warnings,
+    /// replacing whatever was recorded from the previous build. Returns
+    /// whether any errors were present.
+    async fn record_diagnostics(&self, compiler: &Compiler) -> bool {
+        let mut diagnostics = Vec::new();
+        for diagnostic in compiler.compilation.get_errors() {
+            tracing::warn!("{:?}", diagnostic);
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format!("{:?}", diagnostic),
+            });
+        }
+        for diagnostic in compiler.compilation.get_warnings() {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                message: format!("{:?}", diagnostic),
+            });
+        }
+        let has_errors = diagnostics.iter().any(|d| d.severity == Severity::Error);
+        *self.diagnostics.write().await = diagnostics;
+        has_errors
+    }
+
+    /// Push the right live-reload notification for a build that just
+    /// finished: an overlay update if it has errors, otherwise a reload.
+    fn notify_build_finished(&self, has_errors: bool) {
+        let event = if has_errors {
+            ReloadEvent::Errors
+        } else {
+            ReloadEvent::Reload
+        };
+        let _ = self.reload_tx.send(event);
+    }
+
+    /// Bump the build generation counter, marking a build attempt as finished
+    /// and unblocking any request suspended in [`Self::get_asset`].
+    fn bump_generation(&self) {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        *self.build_time.lock().unwrap() = SystemTime::now();
+        self.generation_tx.set(generation);
     }
 
-    pub async fn get_asset(&self, path: impl AsRef<Utf8Path>) -> Option<(Mime, Vec<u8>)> {
+    /// Write a uniquely-named marker file into the compiler's context
+    /// directory and wait for the filesystem watcher to report it, so that
+    /// the caller is guaranteed every event queued ahead of it has drained
+    /// (and any rebuild it triggered has at least started).
+    pub async fn sync(&self) -> Result<CookieId, SyncTimeoutError> {
+        self.sync_timeout(DEFAULT_SYNC_TIMEOUT).await
+    }
+
+    pub async fn sync_timeout(&self, timeout: Duration) -> Result<CookieId, SyncTimeoutError> {
+        let id = self.cookie_counter.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let path = {
+            let compiler = self.compiler.read().await;
+            compiler
+                .options
+                .context
+                .join(format!("{SYNC_COOKIE_PREFIX}{id}"))
+        };
+        let path_key = path.to_string();
+
+        let (tx, rx) = oneshot::channel();
+        self.cookies.lock().unwrap().insert(path_key.clone(), tx);
+
+        if tokio::fs::write(&path, []).await.is_err() {
+            self.cookies.lock().unwrap().remove(&path_key);
+            return Err(SyncTimeoutError(id));
+        }
+
+        let result = tokio::time::timeout(timeout, rx).await;
+        let _ = tokio::fs::remove_file(&path).await;
+
+        match result {
+            Ok(Ok(())) => Ok(id),
+            _ => {
+                self.cookies.lock().unwrap().remove(&path_key);
+                Err(SyncTimeoutError(id))
+            }
+        }
+    }
+
+    pub async fn get_asset(&self, path: impl AsRef<Utf8Path>) -> Result<Option<Asset>, OptionalWatchError> {
+        // Only used to suspend until the first build has finished; the
+        // generation actually stamped on the returned `Asset` is reloaded
+        // below, once the compiler lock guarantees it matches the content
+        // we're about to read.
+        self.generation_rx.clone().get_timeout(self.ready_timeout).await?;
+
         let compiler = self.compiler.read().await;
+        let generation = self.generation.load(Ordering::SeqCst);
         let path = compiler.options.output.path.join(path);
         let fs = &compiler.compilation.output_filesystem;
-        if let Ok(metadata) = fs.stat(&path).await
+        Ok(if let Ok(metadata) = fs.stat(&path).await
             && metadata.is_file
         {
             let content = fs.read_file(&path).await.unwrap();
-            let mime_type = mime_guess::from_path(&path).first_or_octet_stream();
-            Some((mime_type, content))
+            let mime = mime_guess::from_path(&path).first_or_octet_stream();
+            let modified = *self.build_time.lock().unwrap();
+            Some(Asset {
+                mime,
+                len: content.len() as u64,
+                body: AssetBody::Bytes(content),
+                generation,
+                modified,
+            })
         } else {
             None
-        }
+        })
     }
 }
 
 impl EventAggregateHandler for Watching {
     fn on_event_handle(&self, changed_files: FxHashSet<String>, deleted_files: FxHashSet<String>) {
+        {
+            let mut cookies = self.cookies.lock().unwrap();
+            for path in changed_files.iter().chain(deleted_files.iter()) {
+                if let Some(tx) = cookies.remove(path) {
+                    let _ = tx.send(());
+                }
+            }
+        }
+
+        // sync()'s cookie files are only ever meant to resolve the oneshot
+        // above; forwarding them into rebuild() as well would make every
+        // request's sync() call trigger a real rebuild (and broadcast a
+        // reload to every connected client), so strip them out here.
+        let is_cookie = |path: &String| path.contains(SYNC_COOKIE_PREFIX);
+        let changed_files: HashSet<String> =
+            changed_files.into_iter().filter(|path| !is_cookie(path)).collect();
+        let deleted_files: HashSet<String> =
+            deleted_files.into_iter().filter(|path| !is_cookie(path)).collect();
+
+        if changed_files.is_empty() && deleted_files.is_empty() {
+            return;
+        }
+
         let compiler = self.clone();
         tracing::warn!(?changed_files, ?deleted_files);
         tokio::spawn(async move {
-            let _ = compiler
-                .rebuild(
-                    changed_files.into_iter().collect::<HashSet<_>>(),
-                    deleted_files.into_iter().collect::<HashSet<_>>(),
-                )
-                .await;
+            let _ = compiler.rebuild(changed_files, deleted_files).await;
         });
     }
 }